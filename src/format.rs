@@ -0,0 +1,508 @@
+use std::borrow::Cow;
+
+/// Input formats listed in README.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputFormat {
+    Regrf,
+    Dot,
+    Gml,
+    Xgmml,
+    Gexf,
+    Gdf,
+    Graphml,
+}
+
+/// Number of leading bytes inspected first by [`detect_format`].
+const SNIFF_LEN: usize = 4096;
+
+/// Guess the input format from the file contents.
+///
+/// Only the first few KB are inspected, and more only while they still read as top level GML
+/// "key value" pairs. They are read as UTF-16 after a UTF-16 BOM and as UTF-8 otherwise,
+/// replacing invalid sequences.
+/// XML based formats are told apart by their root tag, the others by their leading keywords.
+/// Returns `None` when that header matches no known format.
+pub fn detect_format(bytes: &[u8]) -> Option<InputFormat> {
+    let mut len = SNIFF_LEN;
+    loop {
+        let (head, truncated) = decode_head(bytes, len);
+        match sniff(&head, truncated) {
+            Sniff::Found(format) => return Some(format),
+            Sniff::Incomplete if truncated => len *= 2,
+            Sniff::Incomplete | Sniff::Unknown => return None,
+        }
+    }
+}
+
+enum Sniff {
+    Found(InputFormat),
+    Unknown,
+    /// the text ended inside well-formed GML pairs before "graph ["
+    Incomplete,
+}
+
+fn sniff(text: &str, truncated: bool) -> Sniff {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    if text.starts_with('<') {
+        return detect_xml_format(text).map_or(Sniff::Unknown, Sniff::Found);
+    }
+
+    if text
+        .get(..8)
+        .is_some_and(|head| head.eq_ignore_ascii_case("nodedef>"))
+    {
+        return Sniff::Found(InputFormat::Gdf);
+    }
+
+    if is_dot_header(Tokens { rest: text }) {
+        return Sniff::Found(InputFormat::Dot);
+    }
+
+    // drop the token that may be cut at the end of a truncated head
+    let text = if truncated {
+        text.rfind(char::is_whitespace).map_or("", |i| &text[..i])
+    } else {
+        text
+    };
+    detect_gml(Tokens { rest: text })
+}
+
+/// Decodes at most `len` bytes after the byte order mark, and tells whether bytes were left over.
+fn decode_head(bytes: &[u8], len: usize) -> (Cow<'_, str>, bool) {
+    match bytes {
+        [0xff, 0xfe, rest @ ..] => (
+            Cow::Owned(decode_utf16_lossy(prefix(rest, len), u16::from_le_bytes)),
+            rest.len() > len,
+        ),
+        [0xfe, 0xff, rest @ ..] => (
+            Cow::Owned(decode_utf16_lossy(prefix(rest, len), u16::from_be_bytes)),
+            rest.len() > len,
+        ),
+        _ => (
+            String::from_utf8_lossy(prefix(bytes, len)),
+            bytes.len() > len,
+        ),
+    }
+}
+
+fn prefix(bytes: &[u8], len: usize) -> &[u8] {
+    &bytes[..bytes.len().min(len)]
+}
+
+fn decode_utf16_lossy(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| to_unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn detect_xml_format(mut text: &str) -> Option<InputFormat> {
+    loop {
+        text = text.trim_start();
+        if text.starts_with("<?") {
+            text = &text[text.find("?>")? + 2..];
+        } else if text.starts_with("<!--") {
+            text = &text[text.find("-->")? + 3..];
+        } else if text.starts_with("<!") {
+            text = &text[declaration_len(text)?..];
+        } else {
+            break;
+        }
+    }
+
+    let tag = text.strip_prefix('<')?;
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    // ignore a namespace prefix such as "xgmml:graph"
+    let qualified = &tag[..end];
+    let name = qualified
+        .rsplit_once(':')
+        .map_or(qualified, |(_, name)| name);
+    match name {
+        "regrf" => Some(InputFormat::Regrf),
+        "graphml" => Some(InputFormat::Graphml),
+        "gexf" => Some(InputFormat::Gexf),
+        "graph" => Some(InputFormat::Xgmml),
+        _ => None,
+    }
+}
+
+/// Length of a declaration such as DOCTYPE at the start of `text`.
+///
+/// Quoted literals and an internal subset "[ ... ]" may contain '>' before its own end.
+fn declaration_len(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut in_subset = false;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') => in_subset = true,
+            (None, ']') => in_subset = false,
+            (None, '>') if !in_subset => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Matches the DOT header "[strict] (graph|digraph) [ID] {".
+fn is_dot_header(mut tokens: Tokens) -> bool {
+    let is_keyword = |token: Option<Token>, keyword: &str| match token {
+        Some(Token::Word(word)) => word.eq_ignore_ascii_case(keyword),
+        _ => false,
+    };
+
+    let mut token = tokens.next();
+    if is_keyword(token, "strict") {
+        token = tokens.next();
+    }
+    if !is_keyword(token, "graph") && !is_keyword(token, "digraph") {
+        return false;
+    }
+
+    match tokens.next() {
+        Some(Token::Punct('{')) => true,
+        Some(Token::Word(_)) | Some(Token::Number(_)) | Some(Token::Str(_)) => {
+            tokens.next() == Some(Token::Punct('{'))
+        }
+        // markup such as "<b>x</b>" is split into several tokens
+        Some(Token::Html(_)) => tokens
+            .find(|token| !matches!(token, Token::Html(_) | Token::Word(_) | Token::Number(_)))
+            .is_some_and(|token| token == Token::Punct('{')),
+        _ => false,
+    }
+}
+
+/// Walks the top level "key value" pairs of GML until the "graph [" pair.
+///
+/// A value is a number, a string or a "[ key value ... ]" list.
+/// GML files may start with other keys such as "Creator" before the graph,
+/// but any token breaking the pair structure means the text is not GML.
+fn detect_gml(tokens: Tokens) -> Sniff {
+    let mut depth = 0usize;
+    let mut key = None;
+    let mut is_empty = true;
+    for token in tokens {
+        is_empty = false;
+        match (key, token) {
+            (None, Token::Word(word)) => key = Some(word),
+            (None, Token::Punct(']')) if depth > 0 => depth -= 1,
+            (Some("graph"), Token::Punct('[')) if depth == 0 => {
+                return Sniff::Found(InputFormat::Gml)
+            }
+            (Some(_), Token::Punct('[')) => {
+                depth += 1;
+                key = None;
+            }
+            (Some(_), Token::Number(_)) | (Some(_), Token::Str(_)) => key = None,
+            _ => return Sniff::Unknown,
+        }
+    }
+
+    if is_empty {
+        Sniff::Unknown
+    } else {
+        Sniff::Incomplete
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    /// identifier such as "graph" or "Creator"
+    Word(&'a str),
+    /// integer or real number such as "-1" or "1.5E3"
+    Number(&'a str),
+    /// double quoted string including the quotes
+    Str(&'a str),
+    /// DOT HTML string such as "<<b>x</b>>" including the outer brackets
+    Html(&'a str),
+    Punct(char),
+}
+
+/// Splits DOT/GML like text into tokens, skipping comments.
+///
+/// Stops at an unterminated string or HTML string.
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        loop {
+            self.rest = self.rest.trim_start();
+            if self.rest.starts_with("//") || self.rest.starts_with('#') {
+                self.rest = self.rest.find('\n').map_or("", |i| &self.rest[i..]);
+            } else if self.rest.starts_with("/*") {
+                self.rest = self.rest.find("*/").map_or("", |i| &self.rest[i + 2..]);
+            } else {
+                break;
+            }
+        }
+
+        let c = self.rest.chars().next()?;
+        let (token, len) = if c == '"' {
+            let len = str_len(self.rest)?;
+            (Token::Str(&self.rest[..len]), len)
+        } else if c == '<' {
+            let len = html_len(self.rest)?;
+            (Token::Html(&self.rest[..len]), len)
+        } else if let Some(len) = number_len(self.rest) {
+            (Token::Number(&self.rest[..len]), len)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = self
+                .rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(self.rest.len());
+            (Token::Word(&self.rest[..len]), len)
+        } else {
+            (Token::Punct(c), c.len_utf8())
+        };
+        self.rest = &self.rest[len..];
+        Some(token)
+    }
+}
+
+/// Length of the double quoted string at the start of `text`, skipping DOT's `\"` escapes.
+fn str_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Length of the DOT HTML string at the start of `text`, where '<' and '>' must be balanced.
+fn html_len(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Length of the number at the start of `text`, such as "-1", ".5", "1." or "1.5E3".
+fn number_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let digits_from = |i: usize| {
+        bytes[i.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = match bytes.first() {
+        Some(b'-') | Some(b'+') => 1,
+        _ => 0,
+    };
+    let int_digits = digits_from(len);
+    len += int_digits;
+    let mut has_digits = int_digits > 0;
+    if bytes.get(len) == Some(&b'.') {
+        let frac_digits = digits_from(len + 1);
+        if has_digits || frac_digits > 0 {
+            has_digits = true;
+            len += 1 + frac_digits;
+        }
+    }
+    if !has_digits {
+        return None;
+    }
+
+    if let Some(b'e') | Some(b'E') = bytes.get(len) {
+        let sign = match bytes.get(len + 1) {
+            Some(b'-') | Some(b'+') => 1,
+            _ => 0,
+        };
+        let exp_digits = digits_from(len + 1 + sign);
+        if exp_digits > 0 {
+            len += 1 + sign + exp_digits;
+        }
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_regrf_example() {
+        let bytes = include_bytes!("../document/example.regrf");
+        assert_eq!(detect_format(bytes), Some(InputFormat::Regrf));
+    }
+
+    #[test]
+    fn detect_dot_example() {
+        let bytes = include_bytes!("../document/example.dot");
+        assert_eq!(detect_format(bytes), Some(InputFormat::Dot));
+    }
+
+    #[test]
+    fn detect_dot_headers() {
+        for text in &[
+            "digraph { a -> b }",
+            "graph G { a -- b }",
+            "strict digraph \"quoted id\" { a }",
+            "digraph \"a\\\"b\" { }",
+            "// comment\n/* block */\n# line\nGRAPH {}",
+            "digraph -1 { }",
+            "graph 1.5 { }",
+            "graph .5 { }",
+            "digraph <b>x</b> { }",
+            "digraph <<table><tr><td>x</td></tr></table>> { }",
+        ] {
+            assert_eq!(
+                detect_format(text.as_bytes()),
+                Some(InputFormat::Dot),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn detect_gml() {
+        let text = "graph [\n  node [ id 1 ]\n]";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gml));
+    }
+
+    #[test]
+    fn detect_gml_with_leading_creator_key() {
+        let text = "Creator \"graph [ in a string\"\nVersion 2\ngraph\n[\n  directed 1\n]";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gml));
+    }
+
+    #[test]
+    fn detect_xgmml_after_prolog_comment_and_doctype() {
+        let text = "<?xml version=\"1.0\"?>\n<!-- exported -->\n\
+            <!DOCTYPE graph PUBLIC \"-//John Punin//DTD graph description//EN\">\n\
+            <graph label=\"g\" xmlns=\"http://www.cs.rpi.edu/XGMML\"></graph>";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Xgmml));
+    }
+
+    #[test]
+    fn detect_xgmml_after_doctype_with_internal_subset() {
+        let text = "<!DOCTYPE graph [ <!ENTITY a \"b\"> ]>\n<graph label=\"g\"></graph>";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Xgmml));
+
+        let text = "<!DOCTYPE graph [ <!ENTITY a \"]\"> <!ENTITY b '>'> ]><graph>";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Xgmml));
+
+        let text = "<!DOCTYPE graph SYSTEM \"a[b.dtd\"><graph>";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Xgmml));
+    }
+
+    #[test]
+    fn detect_xgmml_with_namespace_prefix() {
+        let text = "<xgmml:graph xmlns:xgmml=\"http://www.cs.rpi.edu/XGMML\">";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Xgmml));
+    }
+
+    #[test]
+    fn detect_gexf() {
+        let text = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gexf version=\"1.2\"></gexf>";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gexf));
+    }
+
+    #[test]
+    fn detect_gdf() {
+        let text = "nodedef>name VARCHAR,label VARCHAR\na,A\nedgedef>node1 VARCHAR,node2 VARCHAR\n";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gdf));
+    }
+
+    #[test]
+    fn detect_graphml_after_utf8_bom() {
+        let text = "\u{feff}<?xml version=\"1.0\"?>\n<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">";
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Graphml));
+    }
+
+    #[test]
+    fn detect_utf16_with_bom() {
+        let text = "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n<gexf version=\"1.2\"></gexf>";
+
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(detect_format(&bytes), Some(InputFormat::Gexf));
+
+        let mut bytes = vec![0xfe, 0xff];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(detect_format(&bytes), Some(InputFormat::Gexf));
+    }
+
+    #[test]
+    fn detect_with_non_utf8_byte_after_header() {
+        let mut bytes = b"nodedef>name VARCHAR\n".to_vec();
+        bytes.extend_from_slice(&[b'n', 0xe9, b'\n']);
+        assert_eq!(detect_format(&bytes), Some(InputFormat::Gdf));
+
+        let mut bytes = b"graph [\n  node [ label \"".to_vec();
+        bytes.extend_from_slice(&[0xe9, b'"', b' ', b']', b'\n', b']']);
+        assert_eq!(detect_format(&bytes), Some(InputFormat::Gml));
+    }
+
+    #[test]
+    fn detect_gml_after_long_top_level_comments() {
+        let mut text = "Creator \"x\"\n".to_string();
+        text.push_str(&"comment \"padding\"\n".repeat(SNIFF_LEN / 10));
+        text.push_str("graph [ ]");
+        assert!(text.len() > SNIFF_LEN);
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gml));
+
+        // a string crossing the end of the first head
+        let text = format!("comment \"{}\"\ngraph [ ]", "a b ".repeat(SNIFF_LEN));
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Gml));
+
+        let text = format!(
+            "{}graph theory notes",
+            "comment \"padding\"\n".repeat(SNIFF_LEN)
+        );
+        assert_eq!(detect_format(text.as_bytes()), None);
+    }
+
+    #[test]
+    fn detect_xml_without_whitespace_in_head() {
+        let text = format!("<graphml>{}</graphml>", "<node/>".repeat(SNIFF_LEN));
+        assert_eq!(detect_format(text.as_bytes()), Some(InputFormat::Graphml));
+    }
+
+    #[test]
+    fn detect_unknown() {
+        assert_eq!(detect_format(b""), None);
+        assert_eq!(detect_format(b"   \n\t"), None);
+        assert_eq!(
+            detect_format(b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>"),
+            None
+        );
+        assert_eq!(detect_format(b"{\"nodes\": [], \"edges\": []}"), None);
+        assert_eq!(detect_format(&[0xff, 0xfe, 0x00, 0x80]), None);
+        assert_eq!(detect_format(b"graph theory notes"), None);
+        assert_eq!(detect_format(b"digraph [ a ]"), None);
+        assert_eq!(detect_format(b"strict node { }"), None);
+        assert_eq!(detect_format(b"Some notes about graph [ layouts ]"), None);
+        assert_eq!(detect_format(b"int graph [10];"), None);
+    }
+}
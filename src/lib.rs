@@ -0,0 +1,3 @@
+mod format;
+
+pub use format::{detect_format, InputFormat};